@@ -0,0 +1,141 @@
+//! Synthetic large-fixture generators for multi-cursor stress tests.
+//!
+//! Hand-authoring a marker string with thousands of cursors is impractical,
+//! so these build a [`TestState`] programmatically instead. They're meant to
+//! feed [`super::perf::assert_linear`]; see
+//! `multi_cursor_move_right_scales_linearly` below for a worked example.
+
+use crate::movement::{SelRegion, Selection};
+
+use super::perf;
+use super::test_state::TestState;
+
+/// A line of filler text long enough to place several cursors on.
+const LINE: &str = "the quick brown fox jumps over the lazy dog";
+
+/// Builds a line at least `min_len` bytes long by repeating [`LINE`], so
+/// that `min_len` evenly-spaced, non-overlapping offsets always fit on it.
+fn line_of_at_least(min_len: usize) -> String {
+    if min_len <= LINE.len() {
+        return LINE.to_string();
+    }
+
+    let repeats = (min_len + LINE.len() - 1) / LINE.len();
+    LINE.repeat(repeats)[..min_len].to_string()
+}
+
+impl TestState {
+    /// Builds a buffer of `line_count` lines, each with `cursors_per_line`
+    /// carets placed at evenly spaced offsets along it.
+    ///
+    /// Cursors are emitted in ascending offset order, line by line, so the
+    /// resulting selection satisfies [`Selection`]'s sorted,
+    /// non-overlapping invariant. The line is stretched (by repeating
+    /// [`LINE`]) to fit `cursors_per_line` whenever that exceeds the
+    /// filler's natural length, so no two cursors ever land on the same
+    /// offset.
+    pub fn with_cursors(line_count: usize, cursors_per_line: usize) -> Self {
+        let line = line_of_at_least(cursors_per_line);
+        let mut contents = String::with_capacity(line_count * (line.len() + 1));
+        let mut selection = Selection::new();
+
+        for _ in 0..line_count {
+            let line_start = contents.len();
+            contents.push_str(&line);
+            contents.push('\n');
+
+            if cursors_per_line > 0 {
+                let stride = line.len() / cursors_per_line;
+                for cursor in 0..cursors_per_line {
+                    let offset = line_start + cursor * stride;
+                    selection.add_region(SelRegion::caret(offset));
+                }
+            }
+        }
+
+        Self {
+            contents,
+            selection,
+        }
+    }
+
+    /// Builds a buffer by repeating `unit` `times` times, placing a caret
+    /// right after every `cursor_every`-th repetition (`cursor_every == 0`
+    /// means no cursors at all).
+    pub fn from_repeated(unit: &str, times: usize, cursor_every: usize) -> Self {
+        let mut contents = String::with_capacity(unit.len() * times);
+        let mut selection = Selection::new();
+
+        for i in 0..times {
+            contents.push_str(unit);
+            if cursor_every != 0 && (i + 1) % cursor_every == 0 {
+                selection.add_region(SelRegion::caret(contents.len()));
+            }
+        }
+
+        Self {
+            contents,
+            selection,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_cursors_places_one_caret_per_line() {
+        let state = TestState::with_cursors(100, 1);
+        assert_eq!(100, state.selection.len());
+    }
+
+    #[test]
+    fn with_cursors_places_several_carets_per_line() {
+        let state = TestState::with_cursors(10, 4);
+        assert_eq!(40, state.selection.len());
+    }
+
+    #[test]
+    fn with_cursors_stays_non_overlapping_past_line_length() {
+        let state = TestState::with_cursors(5, 100);
+        assert_eq!(500, state.selection.len());
+
+        let offsets: Vec<usize> =
+            state.selection.regions().iter().map(|region| region.start()).collect();
+        let mut sorted = offsets.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(offsets.len(), sorted.len(), "cursor offsets must be unique");
+    }
+
+    #[test]
+    fn from_repeated_places_a_caret_every_n_units() {
+        let state = TestState::from_repeated("ab", 10, 2);
+        assert_eq!("ab".repeat(10), state.contents);
+        assert_eq!(5, state.selection.len());
+    }
+
+    #[test]
+    fn from_repeated_with_no_stride_has_no_cursors() {
+        let state = TestState::from_repeated("ab", 10, 0);
+        assert_eq!(0, state.selection.len());
+    }
+
+    #[test]
+    fn multi_cursor_move_right_scales_linearly() {
+        perf::assert_linear(200, |line_count| {
+            let state = TestState::with_cursors(line_count, 1);
+
+            let moved = state.selection.regions().iter().fold(
+                Selection::new(),
+                |mut selection, region| {
+                    selection.add_region(SelRegion::caret(region.start() + 1));
+                    selection
+                },
+            );
+
+            std::hint::black_box(moved);
+        });
+    }
+}
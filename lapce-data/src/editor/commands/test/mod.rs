@@ -0,0 +1,7 @@
+//! Test-only helpers for exercising editor commands against fixtures.
+
+pub mod bench_fixture;
+pub mod perf;
+pub mod test_state;
+
+pub use test_state::{MultiFileTestState, TestState};
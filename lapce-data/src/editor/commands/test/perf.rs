@@ -0,0 +1,121 @@
+//! A linear-scaling assertion for movement/selection benchmarks, inspired by
+//! rust-analyzer's `assert_linear`.
+
+use std::time::{Duration, Instant};
+
+/// How many geometrically increasing sizes to sample (`n, 2n, 4n, ...`). The
+/// first is discarded as warmup, leaving `SAMPLES - 1` data points.
+const SAMPLES: usize = 4;
+
+/// How many times to repeat the whole measurement, keeping the least noisy
+/// run, before giving up.
+const MAX_ATTEMPTS: usize = 5;
+
+/// The coefficient of variation (stddev / mean) of `time(n) / n` above which
+/// an operation is considered to not scale linearly.
+const MAX_COEFFICIENT_OF_VARIATION: f64 = 0.25;
+
+/// Asserts that `run` scales linearly (or sub-linearly) in `n`.
+///
+/// `run` is called with each of a handful of geometrically increasing sizes
+/// starting at `base_size` (e.g. `base_size, 2*base_size, 4*base_size, ...`);
+/// it is expected to build whatever fixture it needs at that size and
+/// perform the operation being measured. The first size is treated as
+/// warmup and discarded, and the remaining `(size, time)` pairs are checked
+/// by computing `time / size` for each and asserting that this ratio is
+/// roughly constant (via its coefficient of variation) across sizes, which
+/// is what we'd expect of a linear operation but not a quadratic one.
+///
+/// To absorb timer noise the whole measurement is retried up to
+/// [`MAX_ATTEMPTS`] times, keeping the run with the smallest variation, and
+/// only fails if every attempt exceeds [`MAX_COEFFICIENT_OF_VARIATION`]. On
+/// failure, the `(size, time, ratio)` table of the least noisy attempt is
+/// printed so the offending growth curve is visible.
+pub fn assert_linear(base_size: usize, mut run: impl FnMut(usize)) {
+    let sizes: Vec<usize> = (0..SAMPLES).map(|i| base_size << i).collect();
+
+    let mut best: Option<(f64, Vec<(usize, Duration, f64)>)> = None;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let timings: Vec<(usize, Duration)> = sizes
+            .iter()
+            .map(|&size| {
+                let start = Instant::now();
+                run(size);
+                (size, start.elapsed())
+            })
+            .collect();
+
+        // The first, smallest size absorbs one-time costs (allocations,
+        // cache warmup, ...) and is not representative of steady-state
+        // scaling, so it's dropped before computing ratios.
+        let ratios: Vec<(usize, Duration, f64)> = timings[1..]
+            .iter()
+            .map(|&(size, time)| (size, time, time.as_secs_f64() / size as f64))
+            .collect();
+
+        let variation =
+            coefficient_of_variation(ratios.iter().map(|&(_, _, ratio)| ratio));
+
+        if variation <= MAX_COEFFICIENT_OF_VARIATION {
+            return;
+        }
+
+        let is_better = match &best {
+            Some((best_variation, _)) => variation < *best_variation,
+            None => true,
+        };
+        if is_better {
+            best = Some((variation, ratios));
+        }
+    }
+
+    let (variation, ratios) = best.expect("MAX_ATTEMPTS is non-zero");
+    let mut table = String::from("size\ttime\ttime/size\n");
+    for (size, time, ratio) in &ratios {
+        table.push_str(&format!("{size}\t{time:?}\t{ratio:.9}\n"));
+    }
+
+    panic!(
+        "operation does not appear to scale linearly: coefficient of \
+         variation {variation:.3} exceeds threshold {MAX_COEFFICIENT_OF_VARIATION}\n{table}"
+    );
+}
+
+fn coefficient_of_variation(ratios: impl Iterator<Item = f64> + Clone) -> f64 {
+    let count = ratios.clone().count() as f64;
+    let mean = ratios.clone().sum::<f64>() / count;
+    let variance = ratios.map(|ratio| (ratio - mean).powi(2)).sum::<f64>() / count;
+
+    variance.sqrt() / mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_for_a_linear_operation() {
+        assert_linear(1_000, |size| {
+            let data: Vec<u8> = vec![0; size];
+            std::hint::black_box(data.iter().filter(|&&b| b == 1).count());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "does not appear to scale linearly")]
+    fn fails_for_a_quadratic_operation() {
+        assert_linear(200, |size| {
+            let data: Vec<u8> = vec![0; size];
+            let mut count = 0usize;
+            for i in 0..data.len() {
+                for j in 0..data.len() {
+                    if data[i] == data[j] {
+                        count += 1;
+                    }
+                }
+            }
+            std::hint::black_box(count);
+        });
+    }
+}
@@ -3,6 +3,7 @@
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
+    path::PathBuf,
 };
 
 use crate::movement::{SelRegion, Selection};
@@ -19,15 +20,35 @@ impl Debug for TestState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut contents = self.contents.clone();
         let mut inserted = 0;
-        for (id, region) in self.selection.regions().iter().enumerate() {
-            let marker = format!("<${id}>");
-            contents.insert_str(region.start() + inserted, &marker);
+
+        // For each region, `first`/`last` are its text positions in reading
+        // order, and `backward` records whether the head (caret) is the one
+        // that comes first, i.e. the selection was extended leftwards.
+        let markers: Vec<(usize, usize, usize, bool)> = self
+            .selection
+            .regions()
+            .iter()
+            .enumerate()
+            .map(|(id, region)| {
+                if region.end() < region.start() {
+                    (id, region.end(), region.start(), true)
+                } else {
+                    (id, region.start(), region.end(), false)
+                }
+            })
+            .collect();
+
+        for &(id, first, _, backward) in &markers {
+            let bar = if backward { "|" } else { "" };
+            let marker = format!("<${id}{bar}>");
+            contents.insert_str(first + inserted, &marker);
             inserted += marker.len();
         }
-        for (id, region) in self.selection.regions().iter().enumerate() {
-            if !region.is_caret() {
-                let marker = format!("</${id}>");
-                contents.insert_str(region.end() + inserted, &marker);
+        for &(id, _, last, backward) in &markers {
+            if !self.selection.regions()[id].is_caret() {
+                let bar = if backward { "|" } else { "" };
+                let marker = format!("</${id}{bar}>");
+                contents.insert_str(last + inserted, &marker);
                 inserted += marker.len();
             }
         }
@@ -49,10 +70,18 @@ impl PartialEq<TestState> for &str {
 }
 
 impl TestState {
+    /// Parses a fixture string into its plain contents and the cursors it
+    /// describes.
+    ///
+    /// A lone `<$0>` marks a caret. A pair `<$0>`...`</$0>` marks a forward
+    /// selection (anchor at the first marker, caret/head at the second). A
+    /// pair with a trailing bar on both markers, `<$0|>`...`</$0|>`, marks a
+    /// *backward* selection instead, i.e. the head is the first marker and
+    /// the anchor is the second.
     pub fn parse(initial: &str) -> Self {
         lazy_static! {
-            static ref START: Regex = Regex::new(r#"<\$(\d+)>"#).unwrap();
-            static ref END: Regex = Regex::new(r#"</\$(\d+)>"#).unwrap();
+            static ref START: Regex = Regex::new(r#"<\$(\d+)(\|)?>"#).unwrap();
+            static ref END: Regex = Regex::new(r#"</\$(\d+)(\|)?>"#).unwrap();
         }
 
         let mut starts = HashMap::new();
@@ -63,9 +92,10 @@ impl TestState {
         let mut contents = initial.to_string();
 
         let mut record_cursor_marker =
-            |captures: Captures, map: &mut HashMap<usize, usize>| {
+            |captures: Captures, map: &mut HashMap<usize, (usize, bool)>| {
                 let whole_match = captures.get(0).unwrap();
                 let id_match = captures.get(1).unwrap();
+                let backward = captures.get(2).is_some();
 
                 let cursor_id = id_match.as_str().parse::<usize>().unwrap();
 
@@ -73,7 +103,7 @@ impl TestState {
                 let end = whole_match.end() - removed;
                 let marker_len = end - start;
 
-                map.insert(cursor_id, start)
+                map.insert(cursor_id, (start, backward))
                     .map(|_| panic!("Duplicate cursor marker: {whole_match:?}"));
 
                 unsafe { contents.as_mut_vec() }.drain(start..end);
@@ -90,9 +120,19 @@ impl TestState {
         }
 
         let mut selection = Selection::new();
-        for (id, start) in starts.into_iter() {
-            let region = if let Some(end) = ends.get(&id).copied() {
-                SelRegion::new(start, end, None)
+        for (id, (start, start_backward)) in starts.into_iter() {
+            let region = if let Some(&(end, end_backward)) = ends.get(&id) {
+                assert_eq!(
+                    start_backward, end_backward,
+                    "cursor {id} has mismatched direction markers"
+                );
+                if start_backward {
+                    // The first marker in the text is the head, the second
+                    // is the anchor.
+                    SelRegion::new(end, start, None)
+                } else {
+                    SelRegion::new(start, end, None)
+                }
             } else {
                 SelRegion::caret(start)
             };
@@ -104,6 +144,142 @@ impl TestState {
             selection,
         }
     }
+
+    /// Parses `before`, runs `op` against it, and asserts that the result
+    /// renders back to `after`, panicking with a line-by-line diff of the
+    /// two marker strings if it doesn't.
+    pub fn check(before: &str, op: impl FnOnce(&mut TestState), after: &str) {
+        let mut state = TestState::parse(before);
+        op(&mut state);
+
+        let actual = state.to_string();
+        if actual != after {
+            panic!(
+                "TestState::check: operation did not produce the expected state\n\
+                 (- expected / + actual)\n{}",
+                line_diff(after, &actual)
+            );
+        }
+    }
+}
+
+/// Renders a minimal line-by-line diff of two marker strings, for use in
+/// [`TestState::check`] failure messages.
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+
+    let mut out = String::new();
+    for i in 0..expected_lines.len().max(actual_lines.len()) {
+        let expected_line = expected_lines.get(i).copied().unwrap_or("");
+        let actual_line = actual_lines.get(i).copied().unwrap_or("");
+
+        if expected_line == actual_line {
+            out.push_str(&format!("  {expected_line}\n"));
+        } else {
+            out.push_str(&format!("- {expected_line}\n"));
+            out.push_str(&format!("+ {actual_line}\n"));
+        }
+    }
+    out
+}
+
+/// A fixture spanning several named buffers, for exercising operations
+/// (go-to-definition, cross-file movement, ...) that can't be expressed
+/// against a single [`TestState`].
+///
+/// The fixture format borrows rust-analyzer's test-utils convention: a line
+/// of the form `//- /src/foo.rs` starts a new file, and everything up to the
+/// next such marker (or the end of the fixture) becomes that file's
+/// contents, parsed the same way [`TestState::parse`] parses a single
+/// buffer. The first file in the fixture is the "active" one.
+#[derive(PartialEq)]
+pub struct MultiFileTestState {
+    pub files: HashMap<PathBuf, TestState>,
+    pub active: PathBuf,
+}
+
+impl MultiFileTestState {
+    pub fn parse(fixture: &str) -> Self {
+        lazy_static! {
+            static ref FILE_MARKER: Regex = Regex::new(r#"(?m)^//- (\S+)[ \t]*\n"#).unwrap();
+        }
+
+        let mut files = HashMap::new();
+        let mut active = None;
+
+        let mut markers = FILE_MARKER.captures_iter(fixture).peekable();
+        if markers.peek().is_none() {
+            panic!("multi-file fixture must contain at least one `//- /path` marker");
+        }
+
+        while let Some(captures) = markers.next() {
+            let whole_match = captures.get(0).unwrap();
+            let path = PathBuf::from(&captures[1]);
+            let start = whole_match.end();
+            let end = markers
+                .peek()
+                .map(|next| next.get(0).unwrap().start())
+                .unwrap_or(fixture.len());
+
+            if active.is_none() {
+                active = Some(path.clone());
+            }
+
+            if files
+                .insert(path.clone(), TestState::parse(&fixture[start..end]))
+                .is_some()
+            {
+                panic!("Duplicate file marker: {path:?}");
+            }
+        }
+
+        Self {
+            files,
+            active: active.unwrap(),
+        }
+    }
+
+    /// The designated active file.
+    pub fn active(&self) -> &TestState {
+        self.files
+            .get(&self.active)
+            .expect("active file must be present in the fixture")
+    }
+
+    /// Mutable access to the designated active file.
+    pub fn active_mut(&mut self) -> &mut TestState {
+        self.files
+            .get_mut(&self.active)
+            .expect("active file must be present in the fixture")
+    }
+}
+
+impl Debug for MultiFileTestState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The active file must come first so that reparsing this output
+        // designates the same file as active again.
+        let mut paths: Vec<_> = self.files.keys().filter(|&path| *path != self.active).collect();
+        paths.sort();
+        paths.insert(0, &self.active);
+
+        for path in paths {
+            writeln!(f, "//- {}", path.display())?;
+            let contents = format!("{:?}", self.files[path]);
+            f.write_str(&contents)?;
+            if !contents.ends_with('\n') {
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for MultiFileTestState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
 }
 
 mod test_state_tests {
@@ -145,4 +321,116 @@ mod test_state_tests {
 
         assert_eq!(text, state.to_string());
     }
+
+    #[test]
+    fn can_parse_forward_selection_direction() {
+        let text = r#"foo<$0>bar</$0>baz"#;
+
+        let state = TestState::parse(text);
+        let region = &state.selection.regions()[0];
+        assert_eq!(3, region.start());
+        assert_eq!(6, region.end());
+        assert_eq!(text, state.to_string());
+    }
+
+    #[test]
+    fn can_parse_backward_selection_direction() {
+        let text = r#"foo<$0|>bar</$0|>baz"#;
+
+        let state = TestState::parse(text);
+        let region = &state.selection.regions()[0];
+        assert_eq!(6, region.start());
+        assert_eq!(3, region.end());
+        assert_eq!(text, state.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "mismatched direction markers")]
+    fn mismatched_direction_markers_panic() {
+        TestState::parse(r#"foo<$0|>bar</$0>baz"#);
+    }
+
+    #[test]
+    fn check_passes_when_op_matches_expected_state() {
+        TestState::check(
+            "foo<$0>bar",
+            |state| {
+                state.selection = TestState::parse("foo<$1>bar").selection;
+            },
+            "foo<$1>bar",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "did not produce the expected state")]
+    fn check_panics_with_a_diff_on_mismatch() {
+        TestState::check("foo<$0>bar", |_| {}, "foo<$1>bar");
+    }
+}
+
+mod multi_file_test_state_tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_multiple_files() {
+        let fixture = r#"
+//- /src/main.rs
+fn main() { foo<$0>(); }
+//- /src/foo.rs
+pub fn foo<$0>() {}
+"#;
+
+        let state = MultiFileTestState::parse(fixture);
+        assert_eq!(2, state.files.len());
+        assert_eq!(PathBuf::from("/src/main.rs"), state.active);
+        assert_eq!("fn main() { foo(); }\n", state.files[&PathBuf::from("/src/main.rs")].contents);
+        assert_eq!("pub fn foo() {}\n", state.files[&PathBuf::from("/src/foo.rs")].contents);
+    }
+
+    #[test]
+    fn active_file_defaults_to_first_marker() {
+        let fixture = r#"//- /a.rs
+a<$0>
+//- /b.rs
+b<$0>
+"#;
+
+        let state = MultiFileTestState::parse(fixture);
+        assert_eq!(PathBuf::from("/a.rs"), state.active);
+        assert_eq!(1, state.active().selection.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "must contain at least one")]
+    fn panics_without_a_file_marker() {
+        MultiFileTestState::parse("fn main() {}");
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate file marker")]
+    fn panics_on_duplicate_file_marker() {
+        let fixture = r#"//- /a.rs
+a<$0>
+//- /a.rs
+a<$0>
+"#;
+
+        MultiFileTestState::parse(fixture);
+    }
+
+    #[test]
+    fn round_trips_with_a_non_alphabetical_active_file() {
+        let fixture = r#"//- /z.rs
+z<$0>
+//- /a.rs
+a<$0>
+"#;
+
+        let state = MultiFileTestState::parse(fixture);
+        assert_eq!(PathBuf::from("/z.rs"), state.active);
+
+        let reparsed = MultiFileTestState::parse(&state.to_string());
+        assert_eq!(PathBuf::from("/z.rs"), reparsed.active);
+        assert_eq!(state, reparsed);
+    }
 }
\ No newline at end of file